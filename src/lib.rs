@@ -7,6 +7,7 @@
 //! * Sending an `AsciiChar` to one of the 4 segments
 //! * Setting or unsetting the dot associated with one of the 4 segments
 //! * Formatting a `f32` to 1 to 4 segments
+//! * Scrolling a message longer than 4 characters across the display
 //!
 //! ## Example
 //!
@@ -60,9 +61,9 @@
 //!    ht16k33.write_display_buffer().unwrap()
 //!```
 //! 
-//! ## Performance warning
+//! ## Performance
 //!
-//! Due to the api of the ht16k33 crate the display buffer is not directly accessible so each LED that makes up the character is updated sequentially. The way the hardware on this backpack is set up allows a character to be updated by setting a single 16-bit value in the buffer. Iterating over each bit of the 16 every update is clearly not optimal but it's sufficiently fast for my current usage. If the ht16k33 crate is updated to grant mut access to the buffer this can be improved.
+//! The `ht16k33` crate exposes data-only buffer accessors that let a whole digit be set with two byte writes instead of toggling each of the 16 LEDs individually, so `update_bits` now writes a character's font value straight into the two `DisplayData` bytes that make up its row pair.
 
 #![no_std]
 #![deny(warnings, missing_docs)]
@@ -74,7 +75,6 @@ use embedded_hal::blocking::i2c::{Write, WriteRead};
 use ht16k33::{
     DisplayDataAddress,
     DisplayData,
-    LedLocation, 
     HT16K33,
     COMMONS_SIZE,
 };
@@ -85,6 +85,8 @@ pub use ascii::AsciiChar;
 pub enum Error {
     /// Error indicating there aren't enough digits to display the given float value
     InsufficientDigits,
+    /// Error indicating the requested base isn't supported (must be between 2 and 36 inclusive)
+    InvalidBase,
 }
 
 /// Trait enabling using the Adafruit 14-segment LED Alphanumeric Backpack
@@ -97,6 +99,19 @@ pub trait AlphaNum4<E> {
     fn update_buffer_with_char(&mut self, index: Index, value: AsciiChar);
     /// Update the buffer with a formatted float not starting before the specified index
     fn update_buffer_with_float(&mut self, index: Index, value: f32, fractional_digits: u8, base: u8) -> Result<(), Error>;
+    /// Update the buffer with a raw 16-bit font value at the specified index, bypassing the font tables
+    fn update_buffer_with_raw(&mut self, index: Index, value: u16);
+    /// Advance a [`ScrollState`] by one position and render its current 4-character window.
+    /// Returns `true` if this call wrapped the message back to the start.
+    fn update_buffer_with_scroll(&mut self, state: &mut ScrollState) -> bool;
+    /// Update the buffer with a string not starting before the specified index. A `.` doesn't
+    /// consume a digit of its own, it instead lights the dot of the digit just written. A
+    /// leading `.` with no preceding digit is ignored
+    fn update_buffer_with_str(&mut self, index: Index, string: &str) -> Result<(), Error>;
+    /// Update the buffer with an integer formatted in the given base, not starting before the
+    /// specified index. Unlike [`AlphaNum4::update_buffer_with_float`] this never goes through
+    /// `f32`, so it doesn't lose precision on large values
+    fn update_buffer_with_int(&mut self, index: Index, value: i64, base: u8) -> Result<(), Error>;
 }
 
 /// The index of a segment
@@ -135,12 +150,7 @@ impl From<u8> for Index {
     }
 }
 
-fn set_bit<I2C, E>(display: &mut HT16K33<I2C>, index: Index, bit: u8, on: bool) 
-where
-    I2C: 
-        Write<Error = E> + 
-        WriteRead<Error = E> +  
-{
+fn set_bit<I2C>(display: &mut HT16K33<I2C>, index: Index, bit: u8, on: bool) {
     debug_assert!((bit as usize) < (COMMONS_SIZE * 2));
     let index = u8::from(index) * 2;
     let row = DisplayDataAddress::from_bits_truncate(if bit < 8 {
@@ -148,22 +158,50 @@ where
     } else {
         index + 1
     });
-    let common = DisplayData::from_bits_truncate(1 << (bit % 8));
-    display.update_display_buffer(
-        LedLocation { row, common },
-        on,
-    );
+    let mask = 1 << (bit % 8);
+    let mut byte = display.display_buffer_byte(row).bits();
+    if on {
+        byte |= mask;
+    } else {
+        byte &= !mask;
+    }
+    display.set_display_buffer_byte(row, DisplayData::from_bits_truncate(byte));
 }
 
-fn update_bits<I2C, E>(display: &mut HT16K33<I2C>, index: Index, bits: u16) 
-where
-    I2C: 
-        Write<Error = E> + 
-        WriteRead<Error = E> +  
-{
-    for i in 0..16 {
-        let on = ((bits >> i) & 1) == 1;
-        set_bit(display, index, i, on);
+// Writes a whole digit in one shot: the low 8 bits of `bits` go to the digit's
+// first `DisplayData` row and the high 8 bits to the second, instead of
+// toggling all 16 LEDs one at a time.
+fn update_bits<I2C>(display: &mut HT16K33<I2C>, index: Index, bits: u16) {
+    let index = u8::from(index) * 2;
+    let low = DisplayDataAddress::from_bits_truncate(index);
+    let high = DisplayDataAddress::from_bits_truncate(index + 1);
+    display.set_display_buffer_byte(low, DisplayData::from_bits_truncate(bits as u8));
+    display.set_display_buffer_byte(high, DisplayData::from_bits_truncate((bits >> 8) as u8));
+}
+
+/// Tracks the scroll position of a message being displayed a 4-character window at a time,
+/// for use with [`AlphaNum4::update_buffer_with_scroll`]
+pub struct ScrollState<'a> {
+    text: &'a str,
+    gap: usize,
+    cursor: usize,
+}
+
+impl<'a> ScrollState<'a> {
+    /// Create a new `ScrollState` for `text`, with a 4 character gap of blank columns between
+    /// the end of the message and its repeat
+    pub fn new(text: &'a str) -> Self {
+        ScrollState::with_gap(text, 4)
+    }
+
+    /// Create a new `ScrollState` for `text`, with `gap` blank columns between the end of the
+    /// message and its repeat
+    pub fn with_gap(text: &'a str, gap: usize) -> Self {
+        ScrollState {
+            text,
+            gap,
+            cursor: 0,
+        }
     }
 }
 
@@ -189,15 +227,112 @@ where
         let bits = ASCII_FONT_TABLE[value.as_byte() as usize];
         update_bits(self, index, bits);
     }
+    /// Update the buffer with a raw 16-bit font value at the specified index, bypassing the font tables
+    fn update_buffer_with_raw(&mut self, index: Index, value: u16) {
+        update_bits(self, index, value);
+    }
+    /// Advance a [`ScrollState`] by one position and render its current 4-character window.
+    /// Returns `true` if this call wrapped the message back to the start.
+    fn update_buffer_with_scroll(&mut self, state: &mut ScrollState) -> bool {
+        let len = state.text.len() + state.gap;
+
+        // Nothing to scroll (empty text with no gap): blank the display and report wrapped
+        // rather than dividing by zero below
+        if len == 0 {
+            for i in 0..4u8 {
+                update_bits(self, i.into(), 0);
+            }
+            return true;
+        }
+
+        for i in 0..4u8 {
+            let pos = (state.cursor + i as usize) % len;
+            let bits = if pos < state.text.len() {
+                let byte = state.text.as_bytes()[pos] as usize;
+                ASCII_FONT_TABLE.get(byte).copied().unwrap_or(0)
+            } else {
+                0
+            };
+            update_bits(self, i.into(), bits);
+        }
+
+        state.cursor = (state.cursor + 1) % len;
+        state.cursor == 0
+    }
+    /// Update the buffer with a string not starting before the specified index. A `.` doesn't
+    /// consume a digit of its own, it instead lights the dot of the digit just written. A
+    /// leading `.` with no preceding digit is ignored
+    fn update_buffer_with_str(&mut self, index: Index, string: &str) -> Result<(), Error> {
+        let mut pos = u8::from(index);
+        let mut last_pos = None;
+
+        for c in string.chars() {
+            if c == '.' {
+                // A leading dot has no preceding digit to attach to, and lighting the dot at
+                // `pos` now would just be erased when the next character is written there
+                if let Some(last_pos) = last_pos {
+                    self.update_buffer_with_dot(last_pos.into(), true);
+                }
+                continue;
+            }
+
+            if pos > 3 {
+                return Err(Error::InsufficientDigits);
+            }
+
+            // Non-ASCII characters have no font entry; render them blank rather than panicking,
+            // same as update_buffer_with_scroll does for out-of-table bytes
+            let bits = if c.is_ascii() {
+                ASCII_FONT_TABLE.get(c as usize).copied().unwrap_or(0)
+            } else {
+                0
+            };
+            update_bits(self, pos.into(), bits);
+            last_pos = Some(pos);
+            pos += 1;
+        }
+
+        Ok(())
+    }
     /// Update the buffer with a formatted float not starting before the specified index
     /// The logic for this is copied mostly from from the adafruit library. Only difference is this allows the start index to be > 0
-    fn update_buffer_with_float(&mut self, index: Index, mut value: f32, mut fractional_digits: u8, base: u8) -> Result<(), Error> {
-        let index = u8::from(index);
+    fn update_buffer_with_float(&mut self, index: Index, mut value: f32, fractional_digits: u8, base: u8) -> Result<(), Error> {
+        let start_index = u8::from(index);
+
+        // Sentinel values don't have a meaningful digit representation, render a spelled out
+        // word instead and bail before any of the float reduction logic below runs
+        if value.is_nan() || value.is_infinite() {
+            let sentinel = if value.is_nan() {
+                "nan"
+            } else if value.is_sign_negative() {
+                "-inf"
+            } else {
+                "inf"
+            };
+            // Right-align within the available width, same as the numeric path does, so a
+            // narrow window keeps the end of the word (and the sign, where it fits) rather than
+            // losing it to truncation from the right
+            let available = (4 - start_index) as usize;
+            let skip = sentinel.len().saturating_sub(available);
+            let visible = &sentinel[skip..];
+
+            let mut pos = 4 - visible.len() as u8;
+            for p in start_index..pos {
+                update_bits(self, p.into(), 0);
+            }
+            for c in visible.chars() {
+                self.update_buffer_with_char(pos.into(), AsciiChar::new(c));
+                pos += 1;
+            }
+            return Ok(());
+        }
+
+        let index = start_index;
 
         // Available digits on display
         let mut numeric_digits = 4 - index;
-        
-        let is_negative = if value < 0. {
+
+        let mut is_negative = if value < 0. {
             // The sign will take up one digit
             numeric_digits -= 1;
             // Flip the sign to do the rest of the formatting
@@ -210,10 +345,14 @@ where
         let base = base as u32;
         let basef = base as f32;
 
+        // Track as a signed counter so reducing it below zero (rather than wrapping a u8) can be
+        // caught and turned into an error
+        let mut fractional_digits = fractional_digits as i16;
+
         // Work out the multiplier needed to get all fraction digits into an integer
         let mut to_int_factor = base.pow(fractional_digits as u32) as f32;
 
-        // Get an integer containing digits to be displayed 
+        // Get an integer containing digits to be displayed
         let mut display_number = ((value * to_int_factor) + 0.5) as u32;
 
         // Calculate the upper bound given the number of digits available
@@ -221,14 +360,20 @@ where
 
         // If the number is too large, reduce fractional digits
         while display_number >= too_big {
+            if fractional_digits <= 0 {
+                return Err(Error::InsufficientDigits);
+            }
             fractional_digits -= 1;
             to_int_factor /= basef;
             display_number = ((value * to_int_factor) + 0.5) as u32;
         }
 
-        // Did we lose the decimal?
-        if to_int_factor < 1. {
-            return Err(Error::InsufficientDigits)
+        let fractional_digits = fractional_digits as u8;
+
+        // Rounding (or -0.0 itself) collapsed the value to zero, so there's nothing left to hang
+        // a minus sign off
+        if display_number == 0 {
+            is_negative = false;
         }
 
         // Digit we're working on, less the start position
@@ -237,7 +382,7 @@ where
         if display_number == 0 {
             // Write out the 0
             self.update_buffer_with_digit(
-                (index + (display_pos as u8)).into(), 
+                (index + (display_pos as u8)).into(),
                 0,
             );
             // Move the current pos along
@@ -283,6 +428,68 @@ where
             display_pos -= 1;
         }
 
+        Ok(())
+    }
+    /// Update the buffer with an integer formatted in the given base, not starting before the
+    /// specified index. Unlike [`AlphaNum4::update_buffer_with_float`] this never goes through
+    /// `f32`, so it doesn't lose precision on large values
+    fn update_buffer_with_int(&mut self, index: Index, value: i64, base: u8) -> Result<(), Error> {
+        // base 0/1 can't represent digits and the alpha font only covers up to 'Z'; checked at
+        // runtime (not debug_assert!) since this crate ships with debug-assertions off
+        if !(2..=36).contains(&base) {
+            return Err(Error::InvalidBase);
+        }
+
+        let start = u8::from(index);
+        let available = 4 - start;
+
+        let is_negative = value < 0;
+        let mut n = value.unsigned_abs();
+
+        // Extract digits from least to most significant, AVR-libc style, into a small stack
+        // buffer (never more than 4 since that's all the positions we have anyway)
+        let mut digits = [0u8; 4];
+        let mut count = 0u8;
+        loop {
+            if count as usize == digits.len() {
+                return Err(Error::InsufficientDigits);
+            }
+            digits[count as usize] = (n % base as u64) as u8;
+            n /= base as u64;
+            count += 1;
+            if n == 0 {
+                break;
+            }
+        }
+
+        let sign_width = if is_negative { 1 } else { 0 };
+        if count + sign_width > available {
+            return Err(Error::InsufficientDigits);
+        }
+
+        // Write the digits left-to-right, starting from the rightmost position they reach back
+        // from Index::Four
+        let mut pos = 4 - count;
+        for &digit in digits[..count as usize].iter().rev() {
+            if digit < 10 {
+                self.update_buffer_with_digit(pos.into(), digit);
+            } else {
+                self.update_buffer_with_char(pos.into(), AsciiChar::new((b'A' + (digit - 10)) as char));
+            }
+            pos += 1;
+        }
+
+        if is_negative {
+            pos = 4 - count - 1;
+            self.update_buffer_with_char(pos.into(), AsciiChar::new('-'));
+        }
+
+        // Clear unused leading positions
+        let written_from = 4 - count - sign_width;
+        for p in start..written_from {
+            update_bits(self, p.into(), 0);
+        }
+
         Ok(())
     }
 }
\ No newline at end of file